@@ -0,0 +1,444 @@
+//! A reusable reference implementation of the worklist fixpoint that
+//! `ContextOps::program_clauses_for_env` is documented to compute: seed
+//! a worklist with an environment's own clauses (`Environment::clauses`),
+//! repeatedly apply each clause's immediate elaborations, and stop once
+//! a pass discovers nothing new. Embedders don't have to reinvent this
+//! traversal or its termination argument; they only need to teach their
+//! `DomainGoal` type how to report its own immediate elaborations (see
+//! `Elaboratable` below).
+
+use super::{Context, Environment};
+use std::collections::HashSet;
+
+/// Implemented by domain-goal types that `elaborate_env_clauses` knows
+/// how to elaborate: types that can report the clauses directly
+/// entailed by themselves (e.g. `T: SubTrait` entails `T: SuperTrait`).
+pub trait Elaboratable<C: Context> {
+    /// Returns the clauses immediately entailed by `self` -- one step,
+    /// not the transitive closure. `elaborate_env_clauses` is
+    /// responsible for iterating this to a fixpoint.
+    fn elaborate(&self) -> Vec<C::DomainGoal>;
+}
+
+/// Computes the elaborated closure of `environment`'s own clauses: the
+/// fixpoint obtained by repeatedly applying `Elaboratable::elaborate`
+/// to every clause discovered so far, stopping once a pass yields
+/// nothing new. Because `C::DomainGoal` is already `Eq + Hash`, "seen
+/// before" is just a `HashSet` lookup, which is what guarantees this
+/// terminates whenever the set of clauses elaboration can ever produce
+/// from `environment` is finite.
+pub fn elaborate_env_clauses<C: Context>(environment: &C::Environment) -> Vec<C::DomainGoal>
+where
+    C::DomainGoal: Elaboratable<C>,
+{
+    let mut seen: HashSet<C::DomainGoal> = environment.clauses().into_iter().collect();
+    let mut worklist: Vec<C::DomainGoal> = seen.iter().cloned().collect();
+
+    while let Some(next) = worklist.pop() {
+        for elaborated in next.elaborate() {
+            if seen.insert(elaborated.clone()) {
+                worklist.push(elaborated);
+            }
+        }
+    }
+
+    seen.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::*;
+    use crate::fallible::Fallible;
+    use crate::hh::HhGoal;
+    use crate::{ExClause, SimplifiedAnswer};
+    use std::fmt;
+
+    // A minimal `Context` whose only interesting type is `MockDomainGoal`,
+    // just enough to drive `elaborate_env_clauses`. Every method this
+    // test doesn't exercise is left `unimplemented!()`.
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    enum MockDomainGoal {
+        SubTrait,
+        SuperTrait,
+        WellFormed,
+    }
+
+    impl Elaboratable<MockCtx> for MockDomainGoal {
+        fn elaborate(&self) -> Vec<MockDomainGoal> {
+            match self {
+                // `T: SubTrait` entails `T: SuperTrait`, which in turn
+                // entails `WellFormed(T: SuperTrait)` -- a two-step
+                // chain, so a single non-fixpoint pass would miss
+                // `WellFormed`.
+                MockDomainGoal::SubTrait => vec![MockDomainGoal::SuperTrait],
+                MockDomainGoal::SuperTrait => vec![MockDomainGoal::WellFormed],
+                MockDomainGoal::WellFormed => vec![],
+            }
+        }
+    }
+
+    impl DomainGoal<MockCtx> for MockDomainGoal {
+        fn into_goal(self) -> MockGoal {
+            MockGoal
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockEnv {
+        clauses: Vec<MockDomainGoal>,
+    }
+
+    impl Environment<MockCtx> for MockEnv {
+        fn add_clauses(&self, clauses: impl IntoIterator<Item = MockDomainGoal>) -> Self {
+            let mut merged = self.clauses.clone();
+            merged.extend(clauses);
+            MockEnv { clauses: merged }
+        }
+
+        fn clauses(&self) -> Vec<MockDomainGoal> {
+            self.clauses.clone()
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockGoal;
+
+    impl Goal<MockCtx> for MockGoal {
+        fn cannot_prove() -> Self {
+            MockGoal
+        }
+
+        fn into_hh_goal(self) -> HhGoal<MockCtx> {
+            HhGoal::CannotProve
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct MockUniverseMap;
+
+    impl UniverseMap<MockCtx> for MockUniverseMap {
+        fn map_goal_from_canonical(&self, value: &MockCanonicalGoalInEnv) -> MockCanonicalGoalInEnv {
+            value.clone()
+        }
+
+        fn map_subst_from_canonical(&self, value: &MockCanonicalSubst) -> MockCanonicalSubst {
+            value.clone()
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockGoalInEnv;
+
+    impl GoalInEnvironment<MockCtx> for MockGoalInEnv {
+        fn environment(&self) -> &MockEnv {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct MockCanonicalGoalInEnv;
+
+    impl CanonicalGoalInEnvironment<MockCtx> for MockCanonicalGoalInEnv {
+        fn substitute(&self, _subst: &MockSubst) -> (MockEnv, MockGoal) {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct MockUCanonicalGoalInEnv;
+
+    impl UCanonicalGoalInEnvironment<MockCtx> for MockUCanonicalGoalInEnv {
+        fn canonical(&self) -> &MockCanonicalGoalInEnv {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn is_trivial_substitution(&self, _canonical_subst: &MockCanonicalSubst) -> bool {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockConstraint;
+
+    impl ConstraintInEnvironment<MockCtx> for MockConstraint {}
+
+    #[derive(Clone, Debug)]
+    struct MockSubst;
+
+    impl Substitution<MockCtx> for MockSubst {}
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockCanonicalSubst;
+
+    impl CanonicalConstrainedSubst<MockCtx> for MockCanonicalSubst {
+        fn empty_constraints(&self) -> bool {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockBindersGoal;
+
+    impl BindersGoal<MockCtx> for MockBindersGoal {}
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockParameter;
+
+    impl Parameter<MockCtx> for MockParameter {}
+
+    #[derive(Debug)]
+    struct MockProgramClause;
+
+    impl ProgramClause<MockCtx> for MockProgramClause {}
+
+    struct MockCache;
+
+    impl Cache<MockCtx> for MockCache {
+        fn lookup(&self, _goal: &MockUCanonicalGoalInEnv) -> Option<Vec<SimplifiedAnswer<MockCtx>>> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn store(
+            &mut self,
+            _goal: MockUCanonicalGoalInEnv,
+            _answers: Vec<SimplifiedAnswer<MockCtx>>,
+            _dependencies: ClauseDependencies<MockCtx>,
+        ) {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn invalidate(&mut self, _changed: &ClauseDependencies<MockCtx>) {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    struct MockUnificationResult;
+
+    impl UnificationResult<MockCtx> for MockUnificationResult {
+        fn into_ex_clause(self, _ex_clause: &mut ExClause<MockCtx>) {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockInferenceTable;
+
+    impl InferenceTable<MockCtx> for MockInferenceTable {
+        type UnificationResult = MockUnificationResult;
+
+        fn new() -> Self {
+            MockInferenceTable
+        }
+
+        fn instantiate_binders_universally(&mut self, _arg: &MockBindersGoal) -> MockGoal {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn instantiate_binders_existentially(&mut self, _arg: &MockBindersGoal) -> MockGoal {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn instantiate_universes<'v>(
+            &mut self,
+            _value: &'v MockUCanonicalGoalInEnv,
+        ) -> &'v MockCanonicalGoalInEnv {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn debug_ex_clause<'v>(&mut self, _value: &'v ExClause<MockCtx>) -> Box<dyn fmt::Debug + 'v> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn debug_goal<'v>(&mut self, _goal: &'v MockGoalInEnv) -> Box<dyn fmt::Debug + 'v> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn canonicalize_goal(&mut self, _value: &MockGoalInEnv) -> MockCanonicalGoalInEnv {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn canonicalize_constrained_subst(
+            &mut self,
+            _subst: MockSubst,
+            _constraints: Vec<MockConstraint>,
+        ) -> MockCanonicalSubst {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn u_canonicalize_goal(
+            &mut self,
+            _value: &MockCanonicalGoalInEnv,
+        ) -> (MockUCanonicalGoalInEnv, MockUniverseMap) {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn fresh_subst_for_goal(&mut self, _goal: &MockCanonicalGoalInEnv) -> MockSubst {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn invert_goal(&mut self, _value: &MockGoalInEnv) -> Option<MockGoalInEnv> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn unify_parameters(
+            &mut self,
+            _environment: &MockEnv,
+            _a: &MockParameter,
+            _b: &MockParameter,
+        ) -> Fallible<MockUnificationResult> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct MockCtx;
+
+    impl ContextOps<MockCtx> for MockCtx {
+        fn is_coinductive(&self, _goal: &MockUCanonicalGoalInEnv) -> bool {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn program_clauses(
+            &self,
+            _environment: &MockEnv,
+            _goal: &MockDomainGoal,
+        ) -> (Vec<MockProgramClause>, ClauseDependencies<MockCtx>) {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn program_clauses_for_env(&self, environment: &MockEnv) -> Vec<MockDomainGoal> {
+            elaborate_env_clauses::<MockCtx>(environment)
+        }
+
+        fn goal_in_environment(_environment: &MockEnv, _goal: MockGoal) -> MockGoalInEnv {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn is_stratified(&self, _goal: &MockUCanonicalGoalInEnv) -> bool {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    impl Aggregate<MockCtx> for MockCtx {
+        fn make_solution(
+            &self,
+            _root_goal: &MockCanonicalGoalInEnv,
+            _simplified_answers: impl IntoIterator<Item = SimplifiedAnswer<MockCtx>>,
+        ) -> Option<()> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    impl TruncateOps<MockCtx> for MockCtx {
+        fn truncate_goal(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _subgoal: &MockGoalInEnv,
+        ) -> Option<MockGoalInEnv> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn truncate_answer(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _subst: &MockSubst,
+        ) -> Option<MockSubst> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    impl ResolventOps<MockCtx> for MockCtx {
+        fn negative_resolvent(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _ex_clause: ExClause<MockCtx>,
+            _goal: &MockGoalInEnv,
+        ) -> Fallible<NegativeResolution<MockCtx>> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn resolvent_clause(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _environment: &MockEnv,
+            _goal: &MockDomainGoal,
+            _subst: &MockSubst,
+            _clause: &MockProgramClause,
+        ) -> Fallible<ExClause<MockCtx>> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+
+        fn apply_answer_subst(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _ex_clause: ExClause<MockCtx>,
+            _selected_goal: &MockGoalInEnv,
+            _answer_table_goal: &MockCanonicalGoalInEnv,
+            _canonical_answer_subst: &MockCanonicalSubst,
+        ) -> Fallible<ExClause<MockCtx>> {
+            unimplemented!("not exercised by the elaboration tests")
+        }
+    }
+
+    impl Context for MockCtx {
+        type InferenceTable = MockInferenceTable;
+        type Environment = MockEnv;
+        type Goal = MockGoal;
+        type DomainGoal = MockDomainGoal;
+        type UniverseMap = MockUniverseMap;
+        type GoalInEnvironment = MockGoalInEnv;
+        type CanonicalGoalInEnvironment = MockCanonicalGoalInEnv;
+        type UCanonicalGoalInEnvironment = MockUCanonicalGoalInEnv;
+        type RegionConstraint = MockConstraint;
+        type Substitution = MockSubst;
+        type CanonicalConstrainedSubst = MockCanonicalSubst;
+        type BindersGoal = MockBindersGoal;
+        type Parameter = MockParameter;
+        type ProgramClause = MockProgramClause;
+        type Solution = ();
+        type Cache = MockCache;
+    }
+
+    #[test]
+    fn closure_reaches_fixpoint_and_dedups() {
+        let env = MockEnv {
+            clauses: vec![MockDomainGoal::SubTrait, MockDomainGoal::SubTrait],
+        };
+
+        let mut closure = elaborate_env_clauses::<MockCtx>(&env);
+        closure.sort();
+
+        let mut expected = vec![
+            MockDomainGoal::SubTrait,
+            MockDomainGoal::SuperTrait,
+            MockDomainGoal::WellFormed,
+        ];
+        expected.sort();
+
+        assert_eq!(closure.len(), 3, "duplicate seed clause must be deduped");
+        assert_eq!(closure, expected, "closure must reach the full two-step fixpoint");
+    }
+
+    #[test]
+    fn wired_through_elaborated_environment() {
+        let ctx = MockCtx;
+        let env = MockEnv {
+            clauses: vec![MockDomainGoal::SubTrait],
+        };
+
+        let elaborated = ctx.elaborated_environment(&env);
+        let mut clauses = elaborated.clauses();
+        clauses.sort();
+
+        let mut expected = vec![
+            MockDomainGoal::SubTrait,
+            MockDomainGoal::SuperTrait,
+            MockDomainGoal::WellFormed,
+        ];
+        expected.sort();
+
+        assert_eq!(clauses, expected);
+    }
+}
@@ -5,6 +5,8 @@ use std::fmt::Debug;
 use std::hash::Hash;
 
 crate mod prelude;
+pub mod elaborate;
+pub mod truncate;
 
 pub trait Context
     : Sized + Clone + Debug + ContextOps<Self> + Aggregate<Self> + TruncateOps<Self> + ResolventOps<Self>
@@ -79,6 +81,14 @@ pub trait Context
     /// completely opaque to the SLG solver; it is produced by
     /// `make_solution`.
     type Solution;
+
+    /// A cache of answers keyed on u-canonicalized goals, shared
+    /// across solve invocations so that identical queries collapse
+    /// instead of being re-expanded from scratch. Mirrors rustc's
+    /// query + dep-graph caching: each stored answer is tagged with
+    /// the `ClauseDependencies` it was derived from, so it can be
+    /// invalidated incrementally when those dependencies change.
+    type Cache: Cache<Self>;
 }
 
 /// "Truncation" (called "abstraction" in the papers referenced below)
@@ -93,6 +103,15 @@ pub trait Context
 ///   - Riguzzi and Swift; ACM Transactions on Computational Logic 2013
 /// - Radial Restraint
 ///   - Grosof and Swift; 2013
+///
+/// A ready-made, depth-bounded implementation of this trait is
+/// available as `truncate::RadialRestraint`; embedders don't have to
+/// reinvent termination logic, they just need their goal and
+/// substitution types to implement `truncate::Truncatable`.
+///
+/// Because a truncated answer over-approximates the real one, any
+/// solution built from one is only sound if it is reported back as
+/// ambiguous rather than certain; see the note on `Aggregate` below.
 pub trait TruncateOps<C: Context> {
     /// If `subgoal` is too large, return a truncated variant (else
     /// return `None`).
@@ -115,18 +134,122 @@ pub trait ContextOps<C: Context> {
     /// True if this is a coinductive goal -- e.g., proving an auto trait.
     fn is_coinductive(&self, goal: &C::UCanonicalGoalInEnvironment) -> bool;
 
-    /// Returns the set of program clauses that might apply to
-    /// `goal`. (This set can be over-approximated, naturally.)
+    /// Returns the set of program clauses that might apply to `goal`
+    /// (this set can be over-approximated, naturally), together with a
+    /// `ClauseDependencies` fingerprint of exactly the clause sets that
+    /// were consulted to produce it. The solver attaches that
+    /// fingerprint to any cached answer built from this call, so
+    /// `Cache::invalidate` always invalidates against the dependencies
+    /// the lookup actually used -- not a separately computed (and
+    /// potentially stale) approximation of them.
     fn program_clauses(
         &self,
         environment: &C::Environment,
         goal: &C::DomainGoal,
-    ) -> Vec<C::ProgramClause>;
+    ) -> (Vec<C::ProgramClause>, ClauseDependencies<C>);
+
+    /// Returns the *elaborated* closure of `environment`'s hypotheses:
+    /// the fixpoint obtained by repeatedly applying elaboration rules
+    /// (e.g. `T: SubTrait` implies `T: SuperTrait`, or `T: Trait`
+    /// implies `WellFormed(T: Trait)`) to the clauses already recorded
+    /// via `Environment::add_clauses`. This mirrors rustc's
+    /// `ProgramClausesForEnv` query, so that local assumptions get to
+    /// participate in resolution exactly like clauses drawn from the
+    /// global program.
+    ///
+    /// Implementations should run a worklist: seed it with the
+    /// clauses already present in `environment`, and for each newly
+    /// discovered clause, compute its immediate elaborations and push
+    /// any that have not been seen before (since `DomainGoal` is
+    /// already `Eq + Hash`, "seen before" is just a `HashSet` lookup).
+    /// The closure is reached, and the worklist empty, once no
+    /// elaboration step yields an unseen clause. The solver calls this
+    /// once per pushed environment.
+    ///
+    /// A ready-made implementation of this worklist is available as
+    /// `elaborate::elaborate_env_clauses`; embedders don't have to
+    /// reinvent the traversal or its termination argument, they just
+    /// need their `DomainGoal` type to implement
+    /// `elaborate::Elaboratable`.
+    fn program_clauses_for_env(&self, environment: &C::Environment) -> Vec<C::DomainGoal>;
+
+    /// Extends `environment` with its own elaborated closure (the
+    /// clauses `program_clauses_for_env` derives from it), so that
+    /// pushing an environment once is enough for its local hypotheses
+    /// to participate in resolution exactly like clauses drawn from
+    /// the global program. The solver calls this once per pushed
+    /// environment, right after it is constructed, and uses the result
+    /// in place of `environment` from then on.
+    fn elaborated_environment(&self, environment: &C::Environment) -> C::Environment {
+        // `program_clauses_for_env` returns the full closure, seed
+        // clauses included, so only the newly-derived ones need to be
+        // added -- otherwise `environment`'s own clauses end up
+        // duplicated in the result.
+        let already_present = environment.clauses();
+        let new_clauses = self
+            .program_clauses_for_env(environment)
+            .into_iter()
+            .filter(|clause| !already_present.contains(clause));
+        environment.add_clauses(new_clauses)
+    }
 
     fn goal_in_environment(environment: &C::Environment, goal: C::Goal) -> C::GoalInEnvironment;
+
+    /// True if selecting `goal` as a *negative* subgoal (i.e. resolving
+    /// `not { goal }`) is well-stratified: `goal` must not depend on
+    /// its own negation through an odd number of negations (an "odd
+    /// cycle"), or the program has no well-defined semantics for it.
+    /// Embedders that never construct `HhGoal::Not` can simply return
+    /// `true` unconditionally; those that do must track polarity
+    /// through their clause graph (see Apt & Bol's stratified logic
+    /// programs) to answer this soundly. The solver consults this
+    /// before selecting a negative literal, and refuses (or delays) it
+    /// when stratification fails.
+    fn is_stratified(&self, goal: &C::UCanonicalGoalInEnvironment) -> bool;
 }
 
 pub trait ResolventOps<C: Context> {
+    /// Resolves the negative literal `Literal::Negative(goal)` selected
+    /// out of `ex_clause.subgoals` (an `HhGoal::Not` that
+    /// `Goal::into_hh_goal` produced) against the current answer set
+    /// for `goal`, implementing negation-as-failure over stratified
+    /// programs:
+    ///
+    /// - `Ok(NegativeResolution::Resolved(ex_clause))` -- `goal` has no
+    ///   answers, so the negation succeeds; the returned ex-clause has
+    ///   the literal removed from its `subgoals`.
+    /// - `Ok(NegativeResolution::Delayed(ex_clause))` -- `goal` still
+    ///   has unresolved existential variables, so the outcome can't be
+    ///   decided yet and the negative literal *flounders*: the returned
+    ///   ex-clause has the literal moved out of `subgoals` and into
+    ///   `delayed_literals`, to be retried (moved back into `subgoals`)
+    ///   once the rest of the derivation has narrowed the existentials
+    ///   `goal` depends on. The derivation is suspended, not discarded.
+    /// - `Err(NoSolution)` -- `goal` has at least one answer, so the
+    ///   negation fails and this derivation is dead.
+    ///
+    /// `goal` is exactly the `C::GoalInEnvironment` carried by the
+    /// `Literal::Negative` being resolved -- the same representation
+    /// `resolvent_clause`'s `selected_goal`/`apply_answer_subst` use for
+    /// their selected subgoals, so callers pass the literal's goal as is
+    /// rather than a canonicalized form of it. Implementations canonicalize `goal`
+    /// themselves (via `InferenceTable::canonicalize_goal`, then
+    /// `u_canonicalize_goal` if they need a table key) as the first step
+    /// of consulting `goal`'s answer set; `ContextOps::is_stratified`
+    /// works on the u-canonical form for the same reason table identity
+    /// does -- it answers "is this the same goal another table already
+    /// tracks", which only the u-canonical form is a stable key for.
+    ///
+    /// Callers are expected to have already u-canonicalized `goal` and
+    /// checked `ContextOps::is_stratified` on it before selecting it as
+    /// a negative literal.
+    fn negative_resolvent(
+        &self,
+        infer: &mut C::InferenceTable,
+        ex_clause: ExClause<C>,
+        goal: &C::GoalInEnvironment,
+    ) -> Fallible<NegativeResolution<C>>;
+
     fn resolvent_clause(
         &self,
         infer: &mut C::InferenceTable,
@@ -146,7 +269,34 @@ pub trait ResolventOps<C: Context> {
     ) -> Fallible<ExClause<C>>;
 }
 
+/// The outcome of `ResolventOps::negative_resolvent` attempting to
+/// resolve a selected `Literal::Negative(goal)`. Unlike the other
+/// `ResolventOps` methods, a negative literal has a third outcome
+/// besides "resolved" and "no solution": it can *flounder*, in which
+/// case the derivation is suspended rather than resolved or killed, so
+/// the ex-clause always comes back (see `Delayed`) instead of being
+/// dropped.
+pub enum NegativeResolution<C: Context> {
+    /// `goal` had no answers: the literal is removed from `subgoals`
+    /// and the negation is proven.
+    Resolved(ExClause<C>),
+
+    /// `goal` still has unresolved existential variables: the literal
+    /// is moved from `subgoals` into `delayed_literals` so the solver
+    /// can retry it later instead of losing the derivation.
+    Delayed(ExClause<C>),
+}
+
 pub trait Aggregate<C: Context> {
+    /// Combines `simplified_answers` into a single `Solution` for
+    /// `root_goal`. Every `SimplifiedAnswer` is built via
+    /// `SimplifiedAnswer::from_ex_clause`, which already sets
+    /// `ambiguous` whenever the originating `ExClause` had
+    /// `answer_was_truncated` set -- a truncated answer
+    /// over-approximates the real one, so reporting it as certain
+    /// would be unsound. Implementations of `make_solution` must
+    /// therefore treat any `ambiguous` answer as approximate rather
+    /// than trusting its substitution outright.
     fn make_solution(
         &self,
         root_goal: &C::CanonicalGoalInEnvironment,
@@ -154,6 +304,115 @@ pub trait Aggregate<C: Context> {
     ) -> Option<C::Solution>;
 }
 
+/// A fingerprint of the program-clause sets a cached answer was
+/// derived from, returned alongside the clauses themselves from
+/// `ContextOps::program_clauses`. A clause edit produces a `changed`
+/// set that is typically a small subset of any one answer's recorded
+/// dependencies, so `Cache::invalidate` does *not* compare
+/// `ClauseDependencies` for equality -- it drops every cached answer
+/// whose dependencies *overlap* `changed` (see `overlaps`), since
+/// depending on even one edited clause is enough to invalidate an
+/// answer.
+pub struct ClauseDependencies<C: Context> {
+    pub domain_goals: Vec<C::DomainGoal>,
+}
+
+impl<C: Context> ClauseDependencies<C> {
+    /// No dependencies -- the identity element for `union`.
+    pub fn empty() -> Self {
+        ClauseDependencies {
+            domain_goals: vec![],
+        }
+    }
+
+    /// True if `self` and `other` share at least one domain goal. This
+    /// is the set-intersection test `Cache::invalidate` uses to decide
+    /// whether a clause edit (`other`) should drop a cached answer
+    /// whose dependencies are `self`.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.domain_goals.iter().any(|g| other.domain_goals.contains(g))
+    }
+
+    /// Merges `other`'s dependencies into `self`. Completing a table
+    /// typically makes many `ContextOps::program_clauses` calls (one
+    /// per subgoal expanded), each returning its own
+    /// `ClauseDependencies`; folding every call's result through
+    /// `union` before passing the total to `Cache::store` is what
+    /// makes the recorded dependencies cover every clause set that was
+    /// actually consulted, rather than just the last call's.
+    pub fn union(mut self, other: Self) -> Self {
+        for goal in other.domain_goals {
+            if !self.domain_goals.contains(&goal) {
+                self.domain_goals.push(goal);
+            }
+        }
+        self
+    }
+}
+
+impl<C: Context> Clone for ClauseDependencies<C> {
+    fn clone(&self) -> Self {
+        ClauseDependencies {
+            domain_goals: self.domain_goals.clone(),
+        }
+    }
+}
+
+impl<C: Context> Debug for ClauseDependencies<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClauseDependencies")
+            .field("domain_goals", &self.domain_goals)
+            .finish()
+    }
+}
+
+impl<C: Context> PartialEq for ClauseDependencies<C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.domain_goals == other.domain_goals
+    }
+}
+
+impl<C: Context> Eq for ClauseDependencies<C> {}
+
+/// A persistent cache of a *table's* answers -- the `SimplifiedAnswer`s
+/// a table produces before `Aggregate::make_solution` combines them
+/// into a final `Solution` -- keyed on u-canonicalized goals. The
+/// solver consults `lookup` before expanding a table at all, and calls
+/// `store` once the table is completed, so that identical queries
+/// across separate solve invocations don't have to be re-derived.
+/// (Caching at the answer level, rather than the aggregated
+/// `Solution`, is what makes consulting the cache *before* expanding a
+/// table possible: a table can't produce a `Solution` until it has
+/// already been expanded.) `invalidate` enables incremental
+/// re-solving: when a crate's clauses are edited, only the cache
+/// entries whose `ClauseDependencies` mention one of the changed
+/// clauses need to be dropped.
+pub trait Cache<C: Context> {
+    /// Returns the cached answers for `goal`, if any were stored.
+    fn lookup(&self, goal: &C::UCanonicalGoalInEnvironment) -> Option<Vec<SimplifiedAnswer<C>>>;
+
+    /// Records `answers` as the complete answer set for `goal`, tagged
+    /// with the `dependencies` it was derived from. Completing a table
+    /// usually makes several `ContextOps::program_clauses` calls (one
+    /// per subgoal expanded); callers must fold each call's
+    /// `ClauseDependencies` together with `ClauseDependencies::union`
+    /// before calling `store`, so `dependencies` here covers every
+    /// clause set the table actually consulted, not just the last
+    /// call's. Passing anything less under-approximates the table's
+    /// true dependencies and causes `invalidate` to miss this entry
+    /// when one of the unrecorded clauses changes.
+    fn store(
+        &mut self,
+        goal: C::UCanonicalGoalInEnvironment,
+        answers: Vec<SimplifiedAnswer<C>>,
+        dependencies: ClauseDependencies<C>,
+    );
+
+    /// Drops every cache entry whose recorded dependencies overlap
+    /// `changed`, forcing those goals to be re-solved on next lookup.
+    fn invalidate(&mut self, changed: &ClauseDependencies<C>);
+}
+
 pub trait UCanonicalGoalInEnvironment<C: Context>: Debug + Clone + Eq + Hash {
     fn canonical(&self) -> &C::CanonicalGoalInEnvironment;
     fn is_trivial_substitution(&self, canonical_subst: &C::CanonicalConstrainedSubst) -> bool;
@@ -170,6 +429,11 @@ pub trait GoalInEnvironment<C: Context>: Debug + Clone + Eq + Ord + Hash {
 pub trait Environment<C: Context>: Debug + Clone + Eq + Ord + Hash {
     // Used by: simplify
     fn add_clauses(&self, clauses: impl IntoIterator<Item = C::DomainGoal>) -> Self;
+
+    /// The clauses already recorded in this environment -- the seed set
+    /// `elaborate::elaborate_env_clauses` starts its worklist from when
+    /// computing `ContextOps::program_clauses_for_env`.
+    fn clauses(&self) -> Vec<C::DomainGoal>;
 }
 
 pub trait InferenceTable<C: Context>: Clone {
@@ -240,6 +504,12 @@ pub trait DomainGoal<C: Context>: Clone + Debug + Eq + Hash + Ord {
 
 pub trait Goal<C: Context>: Clone + Debug + Eq + Hash + Ord {
     fn cannot_prove() -> Self;
+
+    /// Converts this goal into its "head-and-hypotheses" form. This is
+    /// where negative goals enter the picture: a goal built from
+    /// `not { G }` converts into `HhGoal::Not(G)`, which the solver
+    /// resolves via `ResolventOps::negative_resolvent` rather than
+    /// ordinary clause resolution.
     fn into_hh_goal(self) -> HhGoal<C>;
 }
 
@@ -0,0 +1,515 @@
+//! A reusable, depth-bounded implementation of `TruncateOps`, per the
+//! "Radial Restraint" truncation strategy (Grosof & Swift, 2013): terms
+//! whose structural depth exceeds a fixed bound are generalized by
+//! replacing their over-threshold subtrees with fresh existential
+//! inference variables. Embedders no longer have to reinvent
+//! termination logic from scratch; they only need to teach their
+//! goal/substitution types how to report their own depth and how to
+//! truncate themselves at a given depth (see `Truncatable` below).
+
+use super::{Context, TruncateOps};
+use crate::ExClause;
+
+/// Implemented by term types that `RadialRestraint` knows how to
+/// truncate: types that can report their own structural depth, and
+/// that can replace every subtree at or beyond a given depth with a
+/// fresh existential inference variable.
+pub trait Truncatable<C: Context> {
+    /// The structural depth of `self` -- the number of constructor
+    /// applications between the root and the deepest leaf.
+    fn depth(&self) -> usize;
+
+    /// Returns a copy of `self` in which every subtree rooted at
+    /// `depth` or deeper has been replaced by a fresh existential
+    /// variable minted from `infer`. The result is always *more
+    /// general* than `self`.
+    fn truncate_at_depth(&self, infer: &mut C::InferenceTable, depth: usize) -> Self;
+}
+
+/// A depth-bounded truncator: any goal or substitution whose depth
+/// exceeds `max_size` is generalized down to that depth. Because every
+/// truncated term has bounded depth, only finitely many skeletons
+/// exist up to variable renaming, which is what guarantees the SLG
+/// search terminates.
+pub struct RadialRestraint {
+    pub max_size: usize,
+}
+
+impl RadialRestraint {
+    pub fn new(max_size: usize) -> Self {
+        RadialRestraint { max_size }
+    }
+}
+
+impl<C: Context> TruncateOps<C> for RadialRestraint
+where
+    C::GoalInEnvironment: Truncatable<C>,
+    C::Substitution: Truncatable<C>,
+{
+    fn truncate_goal(
+        &self,
+        infer: &mut C::InferenceTable,
+        subgoal: &C::GoalInEnvironment,
+    ) -> Option<C::GoalInEnvironment> {
+        if subgoal.depth() > self.max_size {
+            Some(subgoal.truncate_at_depth(infer, self.max_size))
+        } else {
+            None
+        }
+    }
+
+    fn truncate_answer(
+        &self,
+        infer: &mut C::InferenceTable,
+        subst: &C::Substitution,
+    ) -> Option<C::Substitution> {
+        if subst.depth() > self.max_size {
+            Some(subst.truncate_at_depth(infer, self.max_size))
+        } else {
+            None
+        }
+    }
+}
+
+/// Runs `truncate_goal` against `subgoal` and, if it truncates, marks
+/// `ex_clause.answer_was_truncated` so that the eventual
+/// `SimplifiedAnswer` built from it is downgraded to ambiguous (see
+/// `SimplifiedAnswer::from_ex_clause`). Returns the goal the solver
+/// should actually select: the truncated one if truncation happened,
+/// `subgoal` itself otherwise.
+pub fn truncate_goal_in_ex_clause<C: Context>(
+    truncate: &impl TruncateOps<C>,
+    infer: &mut C::InferenceTable,
+    ex_clause: &mut ExClause<C>,
+    subgoal: &C::GoalInEnvironment,
+) -> C::GoalInEnvironment {
+    match truncate.truncate_goal(infer, subgoal) {
+        Some(truncated) => {
+            ex_clause.answer_was_truncated = true;
+            truncated
+        }
+        None => subgoal.clone(),
+    }
+}
+
+/// Runs `truncate_answer` against `subst` and, if it truncates, marks
+/// `ex_clause.answer_was_truncated` for the same reason as
+/// `truncate_goal_in_ex_clause`. Returns the substitution the solver
+/// should actually record: the truncated one if truncation happened,
+/// `subst` itself otherwise.
+pub fn truncate_answer_in_ex_clause<C: Context>(
+    truncate: &impl TruncateOps<C>,
+    infer: &mut C::InferenceTable,
+    ex_clause: &mut ExClause<C>,
+    subst: &C::Substitution,
+) -> C::Substitution {
+    match truncate.truncate_answer(infer, subst) {
+        Some(truncated) => {
+            ex_clause.answer_was_truncated = true;
+            truncated
+        }
+        None => subst.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::*;
+    use crate::fallible::Fallible;
+    use crate::hh::HhGoal;
+    use crate::{ExClause, SimplifiedAnswer};
+    use std::fmt;
+
+    // A minimal `Context` whose goal/substitution types carry nothing
+    // but a `depth`, just enough to drive `RadialRestraint` and the
+    // `ex_clause`-wiring helpers above. Every method this test doesn't
+    // exercise is left `unimplemented!()`.
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockEnv;
+
+    impl Environment<MockCtx> for MockEnv {
+        fn add_clauses(&self, _clauses: impl IntoIterator<Item = MockDomainGoal>) -> Self {
+            MockEnv
+        }
+
+        fn clauses(&self) -> Vec<MockDomainGoal> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockGoal;
+
+    impl Goal<MockCtx> for MockGoal {
+        fn cannot_prove() -> Self {
+            MockGoal
+        }
+
+        fn into_hh_goal(self) -> HhGoal<MockCtx> {
+            HhGoal::CannotProve
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockDomainGoal;
+
+    impl DomainGoal<MockCtx> for MockDomainGoal {
+        fn into_goal(self) -> MockGoal {
+            MockGoal
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct MockUniverseMap;
+
+    impl UniverseMap<MockCtx> for MockUniverseMap {
+        fn map_goal_from_canonical(&self, value: &MockCanonicalGoalInEnv) -> MockCanonicalGoalInEnv {
+            value.clone()
+        }
+
+        fn map_subst_from_canonical(&self, value: &MockCanonicalSubst) -> MockCanonicalSubst {
+            value.clone()
+        }
+    }
+
+    // The term type under test: depth-tagged so `Truncatable` has
+    // something to truncate.
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockGoalInEnv {
+        depth: usize,
+    }
+
+    impl GoalInEnvironment<MockCtx> for MockGoalInEnv {
+        fn environment(&self) -> &MockEnv {
+            &MockEnv
+        }
+    }
+
+    impl Truncatable<MockCtx> for MockGoalInEnv {
+        fn depth(&self) -> usize {
+            self.depth
+        }
+
+        fn truncate_at_depth(&self, _infer: &mut MockInferenceTable, depth: usize) -> Self {
+            MockGoalInEnv { depth }
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct MockCanonicalGoalInEnv;
+
+    impl CanonicalGoalInEnvironment<MockCtx> for MockCanonicalGoalInEnv {
+        fn substitute(&self, _subst: &MockSubst) -> (MockEnv, MockGoal) {
+            (MockEnv, MockGoal)
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    struct MockUCanonicalGoalInEnv;
+
+    impl UCanonicalGoalInEnvironment<MockCtx> for MockUCanonicalGoalInEnv {
+        fn canonical(&self) -> &MockCanonicalGoalInEnv {
+            &MockCanonicalGoalInEnv
+        }
+
+        fn is_trivial_substitution(&self, _canonical_subst: &MockCanonicalSubst) -> bool {
+            true
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockConstraint;
+
+    impl ConstraintInEnvironment<MockCtx> for MockConstraint {}
+
+    // The second term type under test, alongside `MockGoalInEnv`.
+    #[derive(Clone, Debug)]
+    struct MockSubst {
+        depth: usize,
+    }
+
+    impl Substitution<MockCtx> for MockSubst {}
+
+    impl Truncatable<MockCtx> for MockSubst {
+        fn depth(&self) -> usize {
+            self.depth
+        }
+
+        fn truncate_at_depth(&self, _infer: &mut MockInferenceTable, depth: usize) -> Self {
+            MockSubst { depth }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockCanonicalSubst;
+
+    impl CanonicalConstrainedSubst<MockCtx> for MockCanonicalSubst {
+        fn empty_constraints(&self) -> bool {
+            true
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockBindersGoal;
+
+    impl BindersGoal<MockCtx> for MockBindersGoal {}
+
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    struct MockParameter;
+
+    impl Parameter<MockCtx> for MockParameter {}
+
+    #[derive(Debug)]
+    struct MockProgramClause;
+
+    impl ProgramClause<MockCtx> for MockProgramClause {}
+
+    struct MockCache;
+
+    impl Cache<MockCtx> for MockCache {
+        fn lookup(&self, _goal: &MockUCanonicalGoalInEnv) -> Option<Vec<SimplifiedAnswer<MockCtx>>> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn store(
+            &mut self,
+            _goal: MockUCanonicalGoalInEnv,
+            _answers: Vec<SimplifiedAnswer<MockCtx>>,
+            _dependencies: ClauseDependencies<MockCtx>,
+        ) {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn invalidate(&mut self, _changed: &ClauseDependencies<MockCtx>) {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    struct MockUnificationResult;
+
+    impl UnificationResult<MockCtx> for MockUnificationResult {
+        fn into_ex_clause(self, _ex_clause: &mut ExClause<MockCtx>) {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockInferenceTable;
+
+    impl InferenceTable<MockCtx> for MockInferenceTable {
+        type UnificationResult = MockUnificationResult;
+
+        fn new() -> Self {
+            MockInferenceTable
+        }
+
+        fn instantiate_binders_universally(&mut self, _arg: &MockBindersGoal) -> MockGoal {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn instantiate_binders_existentially(&mut self, _arg: &MockBindersGoal) -> MockGoal {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn instantiate_universes<'v>(
+            &mut self,
+            _value: &'v MockUCanonicalGoalInEnv,
+        ) -> &'v MockCanonicalGoalInEnv {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn debug_ex_clause<'v>(&mut self, _value: &'v ExClause<MockCtx>) -> Box<dyn fmt::Debug + 'v> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn debug_goal<'v>(&mut self, _goal: &'v MockGoalInEnv) -> Box<dyn fmt::Debug + 'v> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn canonicalize_goal(&mut self, _value: &MockGoalInEnv) -> MockCanonicalGoalInEnv {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn canonicalize_constrained_subst(
+            &mut self,
+            _subst: MockSubst,
+            _constraints: Vec<MockConstraint>,
+        ) -> MockCanonicalSubst {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn u_canonicalize_goal(
+            &mut self,
+            _value: &MockCanonicalGoalInEnv,
+        ) -> (MockUCanonicalGoalInEnv, MockUniverseMap) {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn fresh_subst_for_goal(&mut self, _goal: &MockCanonicalGoalInEnv) -> MockSubst {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn invert_goal(&mut self, _value: &MockGoalInEnv) -> Option<MockGoalInEnv> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn unify_parameters(
+            &mut self,
+            _environment: &MockEnv,
+            _a: &MockParameter,
+            _b: &MockParameter,
+        ) -> Fallible<MockUnificationResult> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    struct MockCtx;
+
+    impl ContextOps<MockCtx> for MockCtx {
+        fn is_coinductive(&self, _goal: &MockUCanonicalGoalInEnv) -> bool {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn program_clauses(
+            &self,
+            _environment: &MockEnv,
+            _goal: &MockDomainGoal,
+        ) -> (Vec<MockProgramClause>, ClauseDependencies<MockCtx>) {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn program_clauses_for_env(&self, _environment: &MockEnv) -> Vec<MockDomainGoal> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn goal_in_environment(_environment: &MockEnv, _goal: MockGoal) -> MockGoalInEnv {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn is_stratified(&self, _goal: &MockUCanonicalGoalInEnv) -> bool {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    impl Aggregate<MockCtx> for MockCtx {
+        fn make_solution(
+            &self,
+            _root_goal: &MockCanonicalGoalInEnv,
+            _simplified_answers: impl IntoIterator<Item = SimplifiedAnswer<MockCtx>>,
+        ) -> Option<()> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    impl TruncateOps<MockCtx> for MockCtx {
+        fn truncate_goal(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _subgoal: &MockGoalInEnv,
+        ) -> Option<MockGoalInEnv> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn truncate_answer(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _subst: &MockSubst,
+        ) -> Option<MockSubst> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    impl ResolventOps<MockCtx> for MockCtx {
+        fn negative_resolvent(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _ex_clause: ExClause<MockCtx>,
+            _goal: &MockGoalInEnv,
+        ) -> Fallible<NegativeResolution<MockCtx>> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn resolvent_clause(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _environment: &MockEnv,
+            _goal: &MockDomainGoal,
+            _subst: &MockSubst,
+            _clause: &MockProgramClause,
+        ) -> Fallible<ExClause<MockCtx>> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+
+        fn apply_answer_subst(
+            &self,
+            _infer: &mut MockInferenceTable,
+            _ex_clause: ExClause<MockCtx>,
+            _selected_goal: &MockGoalInEnv,
+            _answer_table_goal: &MockCanonicalGoalInEnv,
+            _canonical_answer_subst: &MockCanonicalSubst,
+        ) -> Fallible<ExClause<MockCtx>> {
+            unimplemented!("not exercised by the truncation tests")
+        }
+    }
+
+    impl Context for MockCtx {
+        type InferenceTable = MockInferenceTable;
+        type Environment = MockEnv;
+        type Goal = MockGoal;
+        type DomainGoal = MockDomainGoal;
+        type UniverseMap = MockUniverseMap;
+        type GoalInEnvironment = MockGoalInEnv;
+        type CanonicalGoalInEnvironment = MockCanonicalGoalInEnv;
+        type UCanonicalGoalInEnvironment = MockUCanonicalGoalInEnv;
+        type RegionConstraint = MockConstraint;
+        type Substitution = MockSubst;
+        type CanonicalConstrainedSubst = MockCanonicalSubst;
+        type BindersGoal = MockBindersGoal;
+        type Parameter = MockParameter;
+        type ProgramClause = MockProgramClause;
+        type Solution = ();
+        type Cache = MockCache;
+    }
+
+    #[test]
+    fn depth_at_bound_is_not_truncated() {
+        let restraint = RadialRestraint::new(3);
+        let mut infer = MockInferenceTable;
+        let mut ex_clause = ExClause::new(MockSubst { depth: 3 });
+
+        let subgoal = MockGoalInEnv { depth: 3 };
+        let selected =
+            truncate_goal_in_ex_clause::<MockCtx>(&restraint, &mut infer, &mut ex_clause, &subgoal);
+
+        assert_eq!(selected, subgoal);
+        assert!(!ex_clause.answer_was_truncated);
+
+        let subst = MockSubst { depth: 3 };
+        let final_subst =
+            truncate_answer_in_ex_clause::<MockCtx>(&restraint, &mut infer, &mut ex_clause, &subst);
+        let answer = SimplifiedAnswer::from_ex_clause(&ex_clause, MockCanonicalSubst);
+
+        assert_eq!(final_subst.depth, 3);
+        assert!(!answer.ambiguous);
+    }
+
+    #[test]
+    fn depth_over_bound_is_truncated_and_marks_ambiguous() {
+        let restraint = RadialRestraint::new(3);
+        let mut infer = MockInferenceTable;
+        let mut ex_clause = ExClause::new(MockSubst { depth: 5 });
+
+        let subgoal = MockGoalInEnv { depth: 5 };
+        let selected =
+            truncate_goal_in_ex_clause::<MockCtx>(&restraint, &mut infer, &mut ex_clause, &subgoal);
+
+        assert_eq!(selected.depth, 3);
+        assert!(ex_clause.answer_was_truncated);
+
+        let answer = SimplifiedAnswer::from_ex_clause(&ex_clause, MockCanonicalSubst);
+        assert!(answer.ambiguous);
+    }
+}
@@ -0,0 +1,10 @@
+//! Many of chalk's internal operations (unifying two parameters,
+//! resolving a literal, ...) may find that there is no solution. That
+//! outcome is reported through this `Result` alias, as `Err(NoSolution)`.
+
+/// The error produced when a chalk operation finds no solution (e.g.
+/// two parameters fail to unify, or a negated goal has an answer).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NoSolution;
+
+pub type Fallible<T> = Result<T, NoSolution>;
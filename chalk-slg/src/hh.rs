@@ -0,0 +1,41 @@
+//! The "head-and-hypotheses" form that every `Goal` reduces to via
+//! `Goal::into_hh_goal`. The SLG solver matches on this form natively;
+//! anything else (like a bare domain goal) it treats opaquely and
+//! hands off to `ContextOps`/`ResolventOps`.
+
+use crate::context::Context;
+
+#[derive(Clone, Debug)]
+pub enum HhGoal<C: Context> {
+    /// `G1, G2, ...` -- every subgoal must be proven.
+    All(Vec<C::Goal>),
+
+    /// `forall<T> { G }` or `exists<T> { G }`.
+    Quantified(QuantifierKind, C::BindersGoal),
+
+    /// `if (C1, C2, ...) { G }` -- `G` may additionally assume the
+    /// given program clauses while it is being proven.
+    Implies(Vec<C::ProgramClause>, C::Goal),
+
+    /// `not { G }` -- proven by negation-as-failure when `G` has no
+    /// answers. Resolved via `ResolventOps::negative_resolvent` rather
+    /// than ordinary clause resolution; see `ContextOps::is_stratified`
+    /// for when it's sound to select this arm at all.
+    Not(C::Goal),
+
+    /// Two parameters that must unify directly.
+    Unify(C::Parameter, C::Parameter),
+
+    /// A domain goal, to be resolved against program clauses.
+    DomainGoal(C::DomainGoal),
+
+    /// The goal that can never be proven.
+    CannotProve,
+}
+
+/// Distinguishes the two ways a `BindersGoal` can be quantified.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuantifierKind {
+    ForAll,
+    Exists,
+}
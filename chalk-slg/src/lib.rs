@@ -0,0 +1,102 @@
+#![feature(crate_visibility_modifier)]
+
+//! `chalk-slg` defines the `Context` abstraction that the SLG
+//! (Simplified Linear resolution with Goals) solver is generic over,
+//! along with the handful of concrete types (`ExClause`,
+//! `SimplifiedAnswer`, `HhGoal`, ...) that the solver and its
+//! embedders share regardless of which `Context` impl is in play.
+
+pub mod context;
+pub mod fallible;
+pub mod hh;
+
+use crate::context::Context;
+
+/// A single subgoal selected out of an `ExClause`, tagged with the
+/// polarity it was selected under.
+#[derive(Clone, Debug)]
+pub enum Literal<C: Context> {
+    /// An ordinary subgoal: succeeds when `G` has an answer.
+    Positive(C::GoalInEnvironment),
+
+    /// A negative subgoal (`not { G }`, i.e. `HhGoal::Not`): succeeds
+    /// when `G` has *no* answers. Resolved via
+    /// `ResolventOps::negative_resolvent`.
+    Negative(C::GoalInEnvironment),
+}
+
+/// An "extended clause": the intermediate form the solver builds up
+/// while resolving a goal against a program clause, consisting of a
+/// candidate substitution/region-constraints for the table's root
+/// goal plus whatever subgoals remain to be proven (some of which may
+/// be selected negatively, see `Literal::Negative`).
+#[derive(Clone, Debug)]
+pub struct ExClause<C: Context> {
+    /// The (partial) substitution for the table's root goal.
+    pub subst: C::Substitution,
+
+    /// Region constraints accumulated while building this ex-clause.
+    pub constraints: Vec<C::RegionConstraint>,
+
+    /// Subgoals that still need to be resolved before this ex-clause
+    /// becomes a complete answer.
+    pub subgoals: Vec<Literal<C>>,
+
+    /// Negative subgoals that floundered -- selected via
+    /// `Literal::Negative` and handed to
+    /// `ResolventOps::negative_resolvent`, which found their goal still
+    /// had unresolved existential variables and so could not yet
+    /// decide whether the negation succeeds or fails (see
+    /// `context::NegativeResolution::Delayed`). An ex-clause with
+    /// a non-empty `delayed_literals` is not yet a complete answer: the
+    /// solver must retry each one (moving it back into `subgoals`) once
+    /// the rest of the ex-clause's subgoals have narrowed the
+    /// existentials it depends on, rather than discarding the
+    /// derivation outright.
+    pub delayed_literals: Vec<Literal<C>>,
+
+    /// Set when this ex-clause was built on top of a goal or
+    /// substitution that `TruncateOps` replaced with a more general,
+    /// truncated term (see `context::truncate`). Because a truncated
+    /// term over-approximates the real one, any `SimplifiedAnswer`
+    /// built from an ex-clause with this flag set must be downgraded
+    /// to ambiguous -- see `SimplifiedAnswer::from_ex_clause`.
+    pub answer_was_truncated: bool,
+}
+
+impl<C: Context> ExClause<C> {
+    /// An ex-clause with no subgoals and no constraints yet -- the
+    /// starting point before resolving against any program clauses.
+    pub fn new(subst: C::Substitution) -> Self {
+        ExClause {
+            subst,
+            constraints: vec![],
+            subgoals: vec![],
+            delayed_literals: vec![],
+            answer_was_truncated: false,
+        }
+    }
+}
+
+/// One way of proving the root goal of a table: a canonicalized
+/// substitution/constraints, plus whether it is a certain answer or
+/// merely an approximation the caller should treat as ambiguous.
+#[derive(Clone, Debug)]
+pub struct SimplifiedAnswer<C: Context> {
+    pub subst: C::CanonicalConstrainedSubst,
+    pub ambiguous: bool,
+}
+
+impl<C: Context> SimplifiedAnswer<C> {
+    /// Builds the answer for a completed `ex_clause`. There is no
+    /// separate ambiguity parameter to override: `ambiguous` is always
+    /// exactly `ex_clause.answer_was_truncated`, since a truncated
+    /// ex-clause was built on an over-generalized subgoal or
+    /// substitution, and reporting it as certain would be unsound.
+    pub fn from_ex_clause(ex_clause: &ExClause<C>, subst: C::CanonicalConstrainedSubst) -> Self {
+        SimplifiedAnswer {
+            subst,
+            ambiguous: ex_clause.answer_was_truncated,
+        }
+    }
+}